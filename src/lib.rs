@@ -0,0 +1,418 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use object::elf;
+use object::{Object, ObjectSection, ObjectSegment, ObjectSymbol, SegmentFlags, SymbolSection};
+use serde::{Deserialize, Serialize};
+
+pub type Addr = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SegmentType {
+    Text,
+    Data,
+    RoData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub addr: Addr,
+    pub ty: SegmentType,
+    pub file_size: u64,
+    pub zero_padding: u64,
+}
+
+/// A symbol's size attributed to the memory class of the segment its address falls into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolAttribution {
+    pub name: String,
+    pub size: u64,
+    pub ty: SegmentType,
+    pub section: Option<String>,
+}
+
+/// A section's size, read directly from the section table rather than summed from the
+/// symbols that happen to fall inside it, so sections with no sized symbols (and any
+/// padding/alignment the linker leaves between symbols) still count towards their total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionAttribution {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfInfo {
+    pub segments: Vec<Segment>,
+    pub _entry: Addr,
+    pub stack_mem_size: Option<u64>,
+    pub heap_mem_size: Option<u64>,
+    /// Populated only when `read_elf_file` is called with `with_breakdown: true`.
+    pub symbols: Vec<SymbolAttribution>,
+    /// Populated only when `read_elf_file` is called with `with_breakdown: true`.
+    pub sections: Vec<SectionAttribution>,
+    /// `PT_LOAD` segments that didn't cleanly fit the classification rules.
+    pub warnings: Vec<SegmentWarning>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentWarningKind {
+    /// Both `PF_W` and `PF_X` are set on the segment.
+    WxViolation,
+    /// No recognized flags were set; the segment was counted as data to avoid undercounting.
+    Unclassified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentWarning {
+    pub addr: Addr,
+    pub p_flags: u32,
+    pub kind: SegmentWarningKind,
+}
+
+/// Rules for attributing a `PT_LOAD` segment's `p_flags` to a [`SegmentType`].
+///
+/// `overrides` are checked first, as exact `p_flags` matches, which lets targets whose
+/// toolchain doesn't follow the usual R/W/X conventions (e.g. a Harvard-architecture VM
+/// that keeps instruction and data memory in separate address spaces) be modeled
+/// accurately. Anything not covered by an override falls back to: `PF_X` set is
+/// instruction memory, `PF_W` without `PF_X` is data, read-only is rodata, and a segment
+/// with none of `PF_R`/`PF_W`/`PF_X` set is counted as data and reported as unclassified.
+#[derive(Debug, Clone, Default)]
+pub struct ClassificationRules {
+    pub overrides: Vec<(u32, SegmentType)>,
+}
+
+/// Parses a `p_flags` value out of an `r`/`w`/`x` letter combination, e.g. `"rx"` or `"rw"`.
+///
+/// Letters may appear in any order and case; any other character is rejected. This is the
+/// counterpart to ELF's own flag letters and is meant for CLI front-ends that accept
+/// `--classify-override` style flag patterns instead of raw `p_flags` integers.
+pub fn flags_from_letters(s: &str) -> Option<u32> {
+    let mut flags = 0u32;
+
+    for c in s.chars() {
+        flags |= match c.to_ascii_lowercase() {
+            'r' => elf::PF_R,
+            'w' => elf::PF_W,
+            'x' => elf::PF_X,
+            _ => return None,
+        };
+    }
+
+    Some(flags)
+}
+
+impl ClassificationRules {
+    fn classify(&self, p_flags: u32) -> (SegmentType, Option<SegmentWarningKind>) {
+        if let Some((_, ty)) = self.overrides.iter().find(|(flags, _)| *flags == p_flags) {
+            return (ty.clone(), None);
+        }
+
+        let exec = p_flags & elf::PF_X != 0;
+        let write = p_flags & elf::PF_W != 0;
+        let read = p_flags & elf::PF_R != 0;
+
+        if exec {
+            let warning = write.then_some(SegmentWarningKind::WxViolation);
+            (SegmentType::Text, warning)
+        } else if write {
+            (SegmentType::Data, None)
+        } else if read {
+            (SegmentType::RoData, None)
+        } else {
+            (SegmentType::Data, Some(SegmentWarningKind::Unclassified))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeSummary {
+    pub instruction_memory: u64,
+    pub data_memory_total: u64,
+    pub data_memory_stack: Option<u64>,
+    pub data_memory_heap: Option<u64>,
+}
+
+impl SizeSummary {
+    pub fn data_memory_fixed(&self) -> u64 {
+        let dynamic = self.data_memory_dynamic().unwrap_or(0);
+        self.data_memory_total - dynamic
+    }
+    pub fn data_memory_dynamic(&self) -> Option<u64> {
+        match (self.data_memory_stack, self.data_memory_heap) {
+            (Some(s), Some(h)) => Some(s + h),
+            (Some(s), None) => Some(s),
+            (None, Some(h)) => Some(h),
+            (None, None) => None,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.instruction_memory + self.data_memory_total
+    }
+
+    pub fn total_fixed(&self) -> u64 {
+        self.instruction_memory + self.data_memory_fixed()
+    }
+
+    pub fn limit_summary(
+        &self,
+        total_limit: Option<u64>,
+        instruction_limit: Option<u64>,
+        data_limit: Option<u64>,
+    ) -> LimitSummary {
+        LimitSummary {
+            total_limit: total_limit.map(|limit| percent(self.total(), limit)),
+            total_fixed_limit: total_limit.map(|limit| percent(self.total_fixed(), limit)),
+            instruction_limit: instruction_limit
+                .map(|limit| percent(self.instruction_memory, limit)),
+            data_limit: data_limit.map(|limit| percent(self.data_memory_total, limit)),
+            data_fixed_limit: data_limit.map(|limit| percent(self.data_memory_fixed(), limit)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitSummary {
+    pub total_limit: Option<u64>,
+    pub total_fixed_limit: Option<u64>,
+    pub instruction_limit: Option<u64>,
+    pub data_limit: Option<u64>,
+    pub data_fixed_limit: Option<u64>,
+}
+
+impl LimitSummary {
+    pub fn any_over_100_percent(&self, fixed_only: bool) -> bool {
+        fn over(opt: Option<u64>) -> bool {
+            opt.map(|x| x > 100).unwrap_or(false)
+        }
+
+        if fixed_only {
+            over(self.total_fixed_limit)
+                || over(self.instruction_limit)
+                || over(self.data_fixed_limit)
+        } else {
+            over(self.total_limit)
+                || over(self.total_fixed_limit)
+                || over(self.instruction_limit)
+                || over(self.data_limit)
+                || over(self.data_fixed_limit)
+        }
+    }
+}
+
+fn percent(val: u64, of: u64) -> u64 {
+    ((val as f64 / of as f64) * 100.0) as u64
+}
+
+/// Finds which segment a symbol address falls into, if any.
+fn classify_addr(segments: &[Segment], addr: Addr) -> Option<SegmentType> {
+    segments
+        .iter()
+        .find(|seg| addr >= seg.addr && addr < seg.addr + seg.file_size + seg.zero_padding)
+        .map(|seg| seg.ty.clone())
+}
+
+/// Reads in an ELF from bytes.
+///
+/// Any errors during reading will be returned.
+///
+/// This reading is *lossy*, only `LOAD` segments are kept as well as the file header.
+///
+/// When `with_breakdown` is set, symbols are additionally walked and attributed to the
+/// memory class of the segment their address falls into, populating `ElfInfo::symbols`,
+/// and every non-empty section's size is recorded from the section table into
+/// `ElfInfo::sections`. This is skipped by default since it's unnecessary work for a
+/// plain limit check.
+///
+/// `rules` governs how each `PT_LOAD` segment's `p_flags` maps to a [`SegmentType`]; see
+/// [`ClassificationRules`]. Every `PT_LOAD` segment is counted towards some memory class,
+/// raising a warning in `ElfInfo::warnings` rather than dropping the segment when its
+/// flags don't cleanly fit.
+pub fn read_elf_file(
+    bytes: &[u8],
+    with_breakdown: bool,
+    rules: &ClassificationRules,
+) -> Result<ElfInfo, object::Error> {
+    let file = object::File::parse(bytes)?;
+
+    let mut info = ElfInfo {
+        segments: vec![],
+        _entry: file.entry(),
+        stack_mem_size: None,
+        heap_mem_size: None,
+        symbols: vec![],
+        sections: vec![],
+        warnings: vec![],
+    };
+
+    for seg in file.segments() {
+        let SegmentFlags::Elf { p_flags } = seg.flags() else { continue };
+
+        let (ty, warning_kind) = rules.classify(p_flags);
+        if let Some(kind) = warning_kind {
+            info.warnings.push(SegmentWarning {
+                addr: seg.address(),
+                p_flags,
+                kind,
+            });
+        }
+
+        let memsize = seg.size();
+        let data = seg.data()?;
+
+        let file_size = data.len() as u64;
+        let padding = memsize - file_size;
+
+        info.segments.push(Segment {
+            addr: seg.address(),
+            ty,
+            file_size,
+            zero_padding: padding,
+        });
+    }
+
+    for sec in file.sections() {
+        let Ok(name) = sec.name() else { continue };
+        let size = sec.size();
+
+        match name {
+            ".stack" => {
+                let val = if size > 0 { Some(size) } else { None };
+                info.stack_mem_size = val;
+            }
+            ".heap" => {
+                let val = if size > 0 { Some(size) } else { None };
+                info.heap_mem_size = val;
+            }
+            _ => {}
+        }
+
+        if with_breakdown && size > 0 {
+            info.sections.push(SectionAttribution {
+                name: name.to_string(),
+                size,
+            });
+        }
+    }
+
+    if with_breakdown {
+        for sym in file.symbols() {
+            let size = sym.size();
+            if size == 0 {
+                continue;
+            }
+
+            let Some(ty) = classify_addr(&info.segments, sym.address()) else {
+                continue;
+            };
+
+            let Ok(name) = sym.name() else { continue };
+
+            let section = match sym.section() {
+                SymbolSection::Section(idx) => file
+                    .section_by_index(idx)
+                    .ok()
+                    .and_then(|sec| sec.name().ok().map(str::to_string)),
+                _ => None,
+            };
+
+            info.symbols.push(SymbolAttribution {
+                name: name.to_string(),
+                size,
+                ty,
+                section,
+            });
+        }
+    }
+
+    Ok(info)
+}
+
+pub fn size_summary(elf: &ElfInfo) -> SizeSummary {
+    let mut summary = SizeSummary {
+        instruction_memory: 0,
+        data_memory_total: 0,
+        data_memory_stack: elf.stack_mem_size,
+        data_memory_heap: elf.heap_mem_size,
+    };
+
+    for seg in &elf.segments {
+        let mem_size = seg.file_size + seg.zero_padding;
+
+        if seg.ty == SegmentType::Text {
+            summary.instruction_memory += mem_size;
+        } else {
+            summary.data_memory_total += mem_size;
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_from_letters_accepts_any_order_and_case() {
+        assert_eq!(flags_from_letters("rwx"), Some(elf::PF_R | elf::PF_W | elf::PF_X));
+        assert_eq!(flags_from_letters("XRW"), Some(elf::PF_R | elf::PF_W | elf::PF_X));
+        assert_eq!(flags_from_letters("x"), Some(elf::PF_X));
+        assert_eq!(flags_from_letters(""), Some(0));
+    }
+
+    #[test]
+    fn flags_from_letters_rejects_unknown_characters() {
+        assert_eq!(flags_from_letters("rwz"), None);
+    }
+
+    #[test]
+    fn classify_exec_is_text() {
+        let rules = ClassificationRules::default();
+        assert_eq!(rules.classify(elf::PF_R | elf::PF_X), (SegmentType::Text, None));
+    }
+
+    #[test]
+    fn classify_exec_and_write_is_wx_violation() {
+        let rules = ClassificationRules::default();
+        assert_eq!(
+            rules.classify(elf::PF_R | elf::PF_W | elf::PF_X),
+            (SegmentType::Text, Some(SegmentWarningKind::WxViolation))
+        );
+    }
+
+    #[test]
+    fn classify_write_without_exec_is_data() {
+        let rules = ClassificationRules::default();
+        assert_eq!(rules.classify(elf::PF_R | elf::PF_W), (SegmentType::Data, None));
+    }
+
+    #[test]
+    fn classify_read_only_is_rodata() {
+        let rules = ClassificationRules::default();
+        assert_eq!(rules.classify(elf::PF_R), (SegmentType::RoData, None));
+    }
+
+    #[test]
+    fn classify_no_flags_is_unclassified_data() {
+        let rules = ClassificationRules::default();
+        assert_eq!(
+            rules.classify(0),
+            (SegmentType::Data, Some(SegmentWarningKind::Unclassified))
+        );
+    }
+
+    #[test]
+    fn classify_override_takes_precedence() {
+        let rules = ClassificationRules {
+            overrides: vec![(elf::PF_R | elf::PF_X, SegmentType::Data)],
+        };
+        assert_eq!(rules.classify(elf::PF_R | elf::PF_X), (SegmentType::Data, None));
+    }
+}
@@ -1,10 +1,14 @@
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, ValueEnum};
 use humansize::{format_size, BINARY};
-use object::elf;
-use object::{Object, ObjectSection, ObjectSegment, SegmentFlags};
+use serde::{Deserialize, Serialize};
+
+use elf_limits::{
+    read_elf_file, size_summary, ClassificationRules, ElfInfo, LimitSummary, SectionAttribution,
+    SegmentType, SegmentWarningKind, SizeSummary, SymbolAttribution,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -17,194 +21,148 @@ struct Args {
     data_mem_limit: Option<String>,
     #[arg(long)]
     instruction_mem_limit: Option<String>,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Print descending tables of the largest symbols per memory class and the largest sections.
+    #[arg(long)]
+    breakdown: bool,
+    /// Number of entries to show per table in `--breakdown` mode.
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+    /// A `--write-baseline` JSON snapshot from a previous run to diff the current sizes against.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Write a JSON size snapshot to this path for later `--baseline` comparisons.
+    ///
+    /// The file is left untouched if the numbers haven't changed since the last write.
+    #[arg(long)]
+    write_baseline: Option<PathBuf>,
+    /// Fail if any metric grows past `--baseline` by more than this, e.g. `1KiB` or `5%`.
+    #[arg(long)]
+    fail_on_growth: Option<String>,
+    /// Exit with a failure code if any `PT_LOAD` segment is unclassified or violates W^X.
+    #[arg(long)]
+    strict: bool,
+    /// Map a segment's `p_flags` to a memory class, e.g. `rwx=text` or `5=data`.
+    ///
+    /// Checked before the default classification rules; see `ClassificationRules` for why
+    /// that's needed. May be given multiple times.
+    #[arg(long = "classify-override")]
+    classify_override: Vec<String>,
     files: Vec<PathBuf>,
 }
 
-pub type Addr = u64;
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum SegmentType {
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
     Text,
-    Data,
-    RoData,
+    Json,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Segment {
-    pub _addr: Addr,
-    pub ty: SegmentType,
-    pub file_size: u64,
-    pub zero_padding: u64,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ElfInfo {
-    pub segments: Vec<Segment>,
-    pub _entry: Addr,
-    pub stack_mem_size: Option<u64>,
-    pub heap_mem_size: Option<u64>,
-}
+/// Sums `symbols` by name within the given memory classes and returns the `top` largest.
+fn top_symbols<'a>(symbols: &'a [SymbolAttribution], tys: &[SegmentType], top: usize) -> Vec<(&'a str, u64)> {
+    use std::collections::HashMap;
 
-pub struct SizeSummary {
-    pub instruction_memory: u64,
-    pub data_memory_total: u64,
-    pub data_memory_stack: Option<u64>,
-    pub data_memory_heap: Option<u64>,
-}
-
-impl SizeSummary {
-    pub fn data_memory_fixed(&self) -> u64 {
-        let dynamic = self.data_memory_dynamic().unwrap_or(0);
-        self.data_memory_total - dynamic
-    }
-    pub fn data_memory_dynamic(&self) -> Option<u64> {
-        match (self.data_memory_stack, self.data_memory_heap) {
-            (Some(s), Some(h)) => Some(s + h),
-            (Some(s), None) => Some(s),
-            (None, Some(h)) => Some(h),
-            (None, None) => None,
-        }
-    }
-
-    pub fn total(&self) -> u64 {
-        self.instruction_memory + self.data_memory_total
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for sym in symbols.iter().filter(|sym| tys.contains(&sym.ty)) {
+        *totals.entry(sym.name.as_str()).or_default() += sym.size;
     }
 
-    pub fn total_fixed(&self) -> u64 {
-        self.instruction_memory + self.data_memory_fixed()
-    }
-
-    pub fn limit_summary(
-        &self,
-        total_limit: Option<u64>,
-        instruction_limit: Option<u64>,
-        data_limit: Option<u64>,
-    ) -> LimitSummary {
-        LimitSummary {
-            total_limit: total_limit.map(|limit| percent(self.total(), limit)),
-            total_fixed_limit: total_limit.map(|limit| percent(self.total_fixed(), limit)),
-            instruction_limit: instruction_limit
-                .map(|limit| percent(self.instruction_memory, limit)),
-            data_limit: data_limit.map(|limit| percent(self.data_memory_total, limit)),
-            data_fixed_limit: data_limit.map(|limit| percent(self.data_memory_fixed(), limit)),
-        }
-    }
+    let mut totals: Vec<(&str, u64)> = totals.into_iter().collect();
+    totals.sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+    totals.truncate(top);
+    totals
 }
 
-pub struct LimitSummary {
-    pub total_limit: Option<u64>,
-    pub total_fixed_limit: Option<u64>,
-    pub instruction_limit: Option<u64>,
-    pub data_limit: Option<u64>,
-    pub data_fixed_limit: Option<u64>,
+/// Sorts `sections` by size, read straight from the section table, and returns the `top`
+/// largest. Unlike symbol sizes, this also accounts for sections with no sized symbols.
+fn top_sections(sections: &[SectionAttribution], top: usize) -> Vec<(&str, u64)> {
+    let mut totals: Vec<(&str, u64)> = sections.iter().map(|sec| (sec.name.as_str(), sec.size)).collect();
+    totals.sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+    totals.truncate(top);
+    totals
 }
 
-impl LimitSummary {
-    pub fn any_over_100_percent(&self, fixed_only: bool) -> bool {
-        fn over(opt: Option<u64>) -> bool {
-            opt.map(|x| x > 100).unwrap_or(false)
-        }
+fn print_breakdown(path: &std::path::Path, elf: &ElfInfo, top: usize) {
+    let hs = |x| format_size(x, BINARY);
 
-        if fixed_only {
-            over(self.total_fixed_limit)
-                || over(self.instruction_limit)
-                || over(self.data_fixed_limit)
-        } else {
-            over(self.total_limit)
-                || over(self.total_fixed_limit)
-                || over(self.instruction_limit)
-                || over(self.data_limit)
-                || over(self.data_fixed_limit)
+    let print_table = |title: &str, entries: Vec<(&str, u64)>| {
+        println!("  {title}:");
+        if entries.is_empty() {
+            println!("    (none)");
         }
-    }
-}
-
-fn percent(val: u64, of: u64) -> u64 {
-    ((val as f64 / of as f64) * 100.0) as u64
-}
-
-/// Reads in an ELF from bytes.
-///
-/// Any errors during reading will be returned.
-///
-/// This reading is *lossy*, only `LOAD` segments are kept as well as the file header.
-pub fn read_elf_file(bytes: &[u8]) -> Result<ElfInfo, object::Error> {
-    let file = object::File::parse(bytes)?;
-
-    let mut info = ElfInfo {
-        segments: vec![],
-        _entry: file.entry(),
-        stack_mem_size: None,
-        heap_mem_size: None,
-    };
-
-    for seg in file.segments() {
-        let SegmentFlags::Elf { p_flags } = seg.flags() else { continue };
-
-        const TEXT_FLAGS: u32 = elf::PF_X | elf::PF_R;
-        const DATA_FLAGS: u32 = elf::PF_R | elf::PF_W;
-        const RODATA_FLAGS: u32 = elf::PF_R;
-        let ty = match p_flags {
-            TEXT_FLAGS => SegmentType::Text,
-            DATA_FLAGS => SegmentType::Data,
-            RODATA_FLAGS => SegmentType::RoData,
-            _ => continue,
-        };
-
-        let memsize = seg.size();
-        let data = seg.data()?;
-
-        let file_size = data.len() as u64;
-        let padding = memsize - file_size;
-
-        info.segments.push(Segment {
-            _addr: seg.address(),
-            ty,
-            file_size,
-            zero_padding: padding,
-        });
-    }
-
-    for sec in file.sections() {
-        let Ok(name) = sec.name() else { continue };
-        let size = sec.size();
-        match name {
-            ".stack" => {
-                let val = if size > 0 { Some(size) } else { None };
-                info.stack_mem_size = val;
-            }
-            ".heap" => {
-                let val = if size > 0 { Some(size) } else { None };
-                info.heap_mem_size = val;
-            }
-            _ => continue,
+        for (name, size) in entries {
+            println!("    {:>10}  {name}", hs(size));
         }
-    }
+    };
 
-    Ok(info)
+    println!("File: {}", path.display());
+
+    print_table(
+        &format!("Top {top} instruction memory contributors"),
+        top_symbols(&elf.symbols, &[SegmentType::Text], top),
+    );
+    print_table(
+        &format!("Top {top} data memory contributors"),
+        top_symbols(&elf.symbols, &[SegmentType::Data, SegmentType::RoData], top),
+    );
+    print_table(
+        &format!("Top {top} sections"),
+        top_sections(&elf.sections, top),
+    );
 }
 
-fn size_summary(binary: &[u8]) -> Result<SizeSummary, object::Error> {
-    let elf = read_elf_file(binary)?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileReport {
+    file: PathBuf,
+    summary: SizeSummary,
+    data_memory_fixed: u64,
+    data_memory_dynamic: Option<u64>,
+    total: u64,
+    total_fixed: u64,
+    limits: LimitSummary,
+    over_limit: bool,
+    /// Populated when `--baseline` is given and a matching entry is found. Left out of a
+    /// `--write-baseline` snapshot so baselines never carry a stale comparison forward.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    baseline_delta: Option<BaselineDelta>,
+}
 
-    let mut summary = SizeSummary {
-        instruction_memory: 0,
-        data_memory_total: 0,
-        data_memory_stack: elf.stack_mem_size,
-        data_memory_heap: elf.heap_mem_size,
-    };
+/// Per-metric deltas of a [`FileReport`] against a `--baseline` entry for the same file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct BaselineDelta {
+    instruction_memory: i64,
+    data_memory_fixed: i64,
+    data_memory_dynamic: Option<i64>,
+    total: i64,
+    total_fixed: i64,
+    grew_past_threshold: bool,
+}
 
-    for seg in elf.segments {
-        let mem_size = seg.file_size + seg.zero_padding;
+fn build_reports(
+    summaries: &[(PathBuf, SizeSummary, LimitSummary)],
+    fixed_only: bool,
+) -> Vec<FileReport> {
+    summaries
+        .iter()
+        .map(|(path, summary, limits)| FileReport {
+            file: path.clone(),
+            data_memory_fixed: summary.data_memory_fixed(),
+            data_memory_dynamic: summary.data_memory_dynamic(),
+            total: summary.total(),
+            total_fixed: summary.total_fixed(),
+            over_limit: limits.any_over_100_percent(fixed_only),
+            summary: summary.clone(),
+            limits: limits.clone(),
+            baseline_delta: None,
+        })
+        .collect()
+}
 
-        if seg.ty == SegmentType::Text {
-            summary.instruction_memory += mem_size;
-        } else {
-            summary.data_memory_total += mem_size;
-        }
+fn print_summaries_json(reports: &[FileReport]) {
+    match serde_json::to_string_pretty(reports) {
+        Ok(json) => println!("{json}"),
+        Err(err) => eprintln!("Could not serialize results to JSON: {err}"),
     }
-
-    Ok(summary)
 }
 
 fn print_summaries(summaries: &[(PathBuf, SizeSummary, LimitSummary)], fixed_only: bool) {
@@ -316,6 +274,32 @@ fn print_summaries(summaries: &[(PathBuf, SizeSummary, LimitSummary)], fixed_onl
     }
 }
 
+fn parse_classification_override(s: &str) -> Result<(u32, SegmentType), String> {
+    let (flags, ty) = s.split_once('=').ok_or_else(|| {
+        "classify override must be of the form <flags>=<type>, e.g. \"rwx=text\"".to_string()
+    })?;
+
+    let flags = if let Some(hex) = flags.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+            .map_err(|err| format!("classify override flags must be a number or r/w/x letters. {err}"))?
+    } else if let Ok(num) = flags.parse::<u32>() {
+        num
+    } else {
+        elf_limits::flags_from_letters(flags).ok_or_else(|| {
+            format!("classify override flags \"{flags}\" must be a number or r/w/x letters")
+        })?
+    };
+
+    let ty = match ty.to_ascii_lowercase().as_str() {
+        "text" => SegmentType::Text,
+        "data" => SegmentType::Data,
+        "rodata" => SegmentType::RoData,
+        other => return Err(format!("classify override type must be text/data/rodata, got \"{other}\"")),
+    };
+
+    Ok((flags, ty))
+}
+
 fn parse_limit(s: &str) -> Result<u64, String> {
     let s = s.to_ascii_lowercase();
 
@@ -361,6 +345,158 @@ fn parse_limit(s: &str) -> Result<u64, String> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum GrowthThreshold {
+    Bytes(u64),
+    Percent(f64),
+}
+
+impl GrowthThreshold {
+    fn is_exceeded(&self, old: u64, new: u64) -> bool {
+        if new <= old {
+            return false;
+        }
+
+        let growth = new - old;
+
+        match self {
+            GrowthThreshold::Bytes(limit) => growth > *limit,
+            GrowthThreshold::Percent(limit) => {
+                (growth as f64 / old.max(1) as f64) * 100.0 > *limit
+            }
+        }
+    }
+}
+
+fn parse_growth_threshold(s: &str) -> Result<GrowthThreshold, String> {
+    if let Some(num) = s.trim().strip_suffix('%') {
+        num.trim()
+            .parse::<f64>()
+            .map(GrowthThreshold::Percent)
+            .map_err(|err| format!("growth threshold must be a percentage like \"5%\". {err}"))
+    } else {
+        parse_limit(s).map(GrowthThreshold::Bytes)
+    }
+}
+
+fn load_baseline(path: &std::path::Path) -> Result<Vec<FileReport>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&contents).map_err(|err| err.to_string())
+}
+
+/// Only (re)writes the baseline file when its contents actually changed, matching the
+/// "don't touch the file if nothing changed" behavior of other snapshot-based tooling.
+fn write_baseline(path: &std::path::Path, reports: &[FileReport]) -> Result<(), String> {
+    let reports: Vec<FileReport> = reports
+        .iter()
+        .cloned()
+        .map(|mut report| {
+            report.baseline_delta = None;
+            report
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&reports).map_err(|err| err.to_string())?;
+
+    if let Ok(existing) = std::fs::read_to_string(path) {
+        if existing == json {
+            return Ok(());
+        }
+    }
+
+    std::fs::write(path, json).map_err(|err| err.to_string())
+}
+
+/// Matches each of `reports` against `baseline` by file path and fills in its
+/// `baseline_delta`. Returns whether any metric grew past `fail_on_growth`, if set.
+fn compute_baseline_deltas(
+    reports: &mut [FileReport],
+    baseline: &[FileReport],
+    fail_on_growth: Option<GrowthThreshold>,
+) -> bool {
+    let mut any_growth_exceeded = false;
+
+    for report in reports.iter_mut() {
+        let Some(base) = baseline.iter().find(|b| b.file == report.file) else {
+            continue;
+        };
+
+        let old_dynamic = base.data_memory_dynamic.unwrap_or(0);
+        let new_dynamic = report.data_memory_dynamic.unwrap_or(0);
+
+        let grew = fail_on_growth.is_some_and(|threshold| {
+            threshold.is_exceeded(base.summary.instruction_memory, report.summary.instruction_memory)
+                || threshold.is_exceeded(base.data_memory_fixed, report.data_memory_fixed)
+                || threshold.is_exceeded(old_dynamic, new_dynamic)
+                || threshold.is_exceeded(base.total, report.total)
+                || threshold.is_exceeded(base.total_fixed, report.total_fixed)
+        });
+
+        any_growth_exceeded |= grew;
+
+        report.baseline_delta = Some(BaselineDelta {
+            instruction_memory: report.summary.instruction_memory as i64
+                - base.summary.instruction_memory as i64,
+            data_memory_fixed: report.data_memory_fixed as i64 - base.data_memory_fixed as i64,
+            data_memory_dynamic: (report.data_memory_dynamic.is_some()
+                || base.data_memory_dynamic.is_some())
+            .then_some(new_dynamic as i64 - old_dynamic as i64),
+            total: report.total as i64 - base.total as i64,
+            total_fixed: report.total_fixed as i64 - base.total_fixed as i64,
+            grew_past_threshold: grew,
+        });
+    }
+
+    any_growth_exceeded
+}
+
+/// Prints the `baseline_delta` of each of `reports` that has one, for `--format text`. JSON
+/// output carries deltas inline on each report instead, so this is never called in JSON mode.
+fn print_baseline_diff_text(reports: &[FileReport]) {
+    use owo_colors::{OwoColorize, Stream::Stdout};
+
+    let fmt = |diff: i64| -> String {
+        let text = format!(
+            "{}{}",
+            if diff > 0 { "+" } else if diff < 0 { "-" } else { "" },
+            format_size(diff.unsigned_abs(), BINARY)
+        );
+
+        if diff > 0 {
+            text.if_supports_color(Stdout, |t| t.red()).to_string()
+        } else if diff < 0 {
+            text.if_supports_color(Stdout, |t| t.green()).to_string()
+        } else {
+            text
+        }
+    };
+
+    let mut printed = 0;
+
+    for report in reports {
+        let Some(delta) = &report.baseline_delta else {
+            continue;
+        };
+
+        if printed > 0 {
+            println!();
+        }
+        printed += 1;
+
+        println!("File: {}", report.file.display());
+        println!("  Instruction memory: {}", fmt(delta.instruction_memory));
+        println!("  Data memory fixed:  {}", fmt(delta.data_memory_fixed));
+        if let Some(dynamic) = delta.data_memory_dynamic {
+            println!("  Data memory dynamic:{}", fmt(dynamic));
+        }
+        println!("  Total memory fixed: {}", fmt(delta.total_fixed));
+        println!("  Total memory:       {}", fmt(delta.total));
+
+        if delta.grew_past_threshold {
+            println!("  File {} grew past --fail-on-growth threshold", report.file.display());
+        }
+    }
+}
+
 fn main() -> ExitCode {
     let args = Args::parse();
 
@@ -409,8 +545,39 @@ fn main() -> ExitCode {
     } else {
         None
     };
+    let fail_on_growth = if let Some(threshold) = args.fail_on_growth {
+        match parse_growth_threshold(&threshold) {
+            Ok(val) => Some(val),
+            Err(err) => {
+                let mut cmd = Args::command();
+                cmd.error(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!("--fail-on-growth value validation error: {err}"),
+                )
+                .exit()
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut classification_rules = ClassificationRules::default();
+    for entry in &args.classify_override {
+        match parse_classification_override(entry) {
+            Ok(pair) => classification_rules.overrides.push(pair),
+            Err(err) => {
+                let mut cmd = Args::command();
+                cmd.error(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!("--classify-override value validation error: {err}"),
+                )
+                .exit()
+            }
+        }
+    }
 
     let mut summaries = vec![];
+    let mut any_strict_violation = false;
 
     for path in &args.files {
         let contents = match std::fs::read(path) {
@@ -421,7 +588,7 @@ fn main() -> ExitCode {
             }
         };
 
-        let summary = match size_summary(&contents) {
+        let elf = match read_elf_file(&contents, args.breakdown, &classification_rules) {
             Ok(val) => val,
             Err(err) => {
                 eprintln!("Error reading ELF binary {}: {err}", path.display());
@@ -429,30 +596,264 @@ fn main() -> ExitCode {
             }
         };
 
+        for warning in &elf.warnings {
+            let reason = match warning.kind {
+                SegmentWarningKind::WxViolation => "violates W^X (both writable and executable)",
+                SegmentWarningKind::Unclassified => "has unrecognized flags; counted as data",
+            };
+            eprintln!(
+                "Warning: {} segment at {:#x} (p_flags={:#x}) {reason}",
+                path.display(),
+                warning.addr,
+                warning.p_flags
+            );
+        }
+
+        if args.strict && !elf.warnings.is_empty() {
+            any_strict_violation = true;
+        }
+
+        let summary = size_summary(&elf);
         let limits = summary.limit_summary(total_limit, instruction_limit, data_limit);
 
-        summaries.push((path.clone(), summary, limits));
+        summaries.push((path.clone(), elf, summary, limits));
     }
 
-    print_summaries(summaries.as_slice(), args.fixed_only);
+    // --breakdown only changes what is printed; limit and baseline gating below
+    // runs the same regardless so e.g. --breakdown --instruction-mem-limit still fails the
+    // build when over limit.
+    if args.breakdown {
+        for (i, (path, elf, ..)) in summaries.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+
+            print_breakdown(path, elf, args.top);
+        }
+    }
+
+    let summaries: Vec<(PathBuf, SizeSummary, LimitSummary)> = summaries
+        .into_iter()
+        .map(|(path, _, summary, limits)| (path, summary, limits))
+        .collect();
+    let mut reports = build_reports(summaries.as_slice(), args.fixed_only);
+
+    let mut any_growth_exceeded = false;
+
+    if let Some(baseline_path) = &args.baseline {
+        match load_baseline(baseline_path) {
+            Ok(baseline) => {
+                any_growth_exceeded =
+                    compute_baseline_deltas(&mut reports, &baseline, fail_on_growth);
+            }
+            Err(err) => {
+                eprintln!(
+                    "Could not read baseline {}: {err}",
+                    baseline_path.display()
+                );
+            }
+        }
+    }
+
+    if let Some(write_baseline_path) = &args.write_baseline {
+        if let Err(err) = write_baseline(write_baseline_path, &reports) {
+            eprintln!(
+                "Could not write baseline {}: {err}",
+                write_baseline_path.display()
+            );
+        }
+    }
+
+    // --breakdown replaces the plain-text summary table with the per-symbol/section tables
+    // printed above, but the JSON report and the baseline diff still carry information
+    // (notably which limit failed) that the breakdown tables don't, so those always run.
+    match args.format {
+        Format::Text => {
+            if !args.breakdown {
+                print_summaries(summaries.as_slice(), args.fixed_only);
+            }
+            if args.baseline.is_some() {
+                println!();
+                print_baseline_diff_text(&reports);
+            }
+        }
+        Format::Json => print_summaries_json(&reports),
+    }
 
     let mut any_over_limit = false;
 
-    for (i, (path, _, lim)) in summaries.into_iter().enumerate() {
+    for (i, (path, _, lim)) in summaries.iter().enumerate() {
         if lim.any_over_100_percent(args.fixed_only) {
             any_over_limit = true;
 
-            if i == 0 {
-                println!();
-            }
+            if args.format == Format::Text {
+                if i == 0 {
+                    println!();
+                }
 
-            println!("File {} exceeds memory limits", path.display());
+                println!("File {} exceeds memory limits", path.display());
+            }
         }
     }
 
-    if any_over_limit {
+    if any_over_limit || any_growth_exceeded || any_strict_violation {
         ExitCode::FAILURE
     } else {
         ExitCode::SUCCESS
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_growth_threshold_parses_bytes() {
+        assert!(matches!(parse_growth_threshold("1KiB"), Ok(GrowthThreshold::Bytes(1024))));
+    }
+
+    #[test]
+    fn parse_growth_threshold_parses_percent() {
+        assert!(matches!(parse_growth_threshold("5%"), Ok(GrowthThreshold::Percent(p)) if p == 5.0));
+    }
+
+    #[test]
+    fn parse_growth_threshold_rejects_garbage_percent() {
+        assert!(parse_growth_threshold("abc%").is_err());
+    }
+
+    #[test]
+    fn growth_threshold_bytes_is_exceeded() {
+        let threshold = GrowthThreshold::Bytes(100);
+        assert!(!threshold.is_exceeded(1000, 1050));
+        assert!(threshold.is_exceeded(1000, 1200));
+        assert!(!threshold.is_exceeded(1000, 900));
+    }
+
+    #[test]
+    fn growth_threshold_percent_is_exceeded() {
+        let threshold = GrowthThreshold::Percent(10.0);
+        assert!(!threshold.is_exceeded(1000, 1050));
+        assert!(threshold.is_exceeded(1000, 1200));
+    }
+
+    #[test]
+    fn parse_classification_override_accepts_letters_hex_and_decimal() {
+        assert_eq!(
+            parse_classification_override("rx=text").unwrap(),
+            (elf_limits::flags_from_letters("rx").unwrap(), SegmentType::Text)
+        );
+        assert_eq!(
+            parse_classification_override("0x5=rodata").unwrap(),
+            (5, SegmentType::RoData)
+        );
+        assert_eq!(parse_classification_override("6=data").unwrap(), (6, SegmentType::Data));
+    }
+
+    #[test]
+    fn parse_classification_override_rejects_malformed_input() {
+        assert!(parse_classification_override("rwx").is_err());
+        assert!(parse_classification_override("rwz=text").is_err());
+        assert!(parse_classification_override("rwx=bogus").is_err());
+    }
+
+    #[test]
+    fn top_symbols_aggregates_by_name_and_filters_by_class() {
+        let symbols = vec![
+            SymbolAttribution {
+                name: "foo".to_string(),
+                size: 10,
+                ty: SegmentType::Text,
+                section: None,
+            },
+            SymbolAttribution {
+                name: "foo".to_string(),
+                size: 20,
+                ty: SegmentType::Text,
+                section: None,
+            },
+            SymbolAttribution {
+                name: "bar".to_string(),
+                size: 5,
+                ty: SegmentType::Data,
+                section: None,
+            },
+        ];
+
+        let top = top_symbols(&symbols, &[SegmentType::Text], 10);
+        assert_eq!(top, vec![("foo", 30)]);
+    }
+
+    #[test]
+    fn top_symbols_truncates_and_sorts_descending() {
+        let symbols = vec![
+            SymbolAttribution { name: "a".to_string(), size: 1, ty: SegmentType::Text, section: None },
+            SymbolAttribution { name: "b".to_string(), size: 3, ty: SegmentType::Text, section: None },
+            SymbolAttribution { name: "c".to_string(), size: 2, ty: SegmentType::Text, section: None },
+        ];
+
+        let top = top_symbols(&symbols, &[SegmentType::Text], 2);
+        assert_eq!(top, vec![("b", 3), ("c", 2)]);
+    }
+
+    #[test]
+    fn top_sections_reads_sizes_straight_from_the_section_table() {
+        let sections = vec![
+            SectionAttribution { name: ".text".to_string(), size: 100 },
+            SectionAttribution { name: ".bss".to_string(), size: 50 },
+        ];
+
+        let top = top_sections(&sections, 10);
+        assert_eq!(top, vec![(".text", 100), (".bss", 50)]);
+    }
+
+    #[test]
+    fn file_report_round_trips_through_json() {
+        let report = FileReport {
+            file: PathBuf::from("a.elf"),
+            summary: SizeSummary {
+                instruction_memory: 100,
+                data_memory_total: 50,
+                data_memory_stack: Some(10),
+                data_memory_heap: None,
+            },
+            data_memory_fixed: 40,
+            data_memory_dynamic: Some(10),
+            total: 150,
+            total_fixed: 140,
+            limits: LimitSummary {
+                total_limit: Some(50),
+                total_fixed_limit: Some(50),
+                instruction_limit: None,
+                data_limit: None,
+                data_fixed_limit: None,
+            },
+            over_limit: false,
+            baseline_delta: None,
+        };
+
+        let json = serde_json::to_string(&report).unwrap();
+        let round_tripped: FileReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.file, report.file);
+        assert_eq!(round_tripped.total, report.total);
+        assert_eq!(round_tripped.baseline_delta, None);
+    }
+
+    #[test]
+    fn file_report_deserializes_baseline_without_delta_field() {
+        // Snapshots written before baseline_delta existed won't have the field at all.
+        let json = r#"{
+            "file": "a.elf",
+            "summary": {"instruction_memory": 1, "data_memory_total": 2, "data_memory_stack": null, "data_memory_heap": null},
+            "data_memory_fixed": 2,
+            "data_memory_dynamic": null,
+            "total": 3,
+            "total_fixed": 3,
+            "limits": {"total_limit": null, "total_fixed_limit": null, "instruction_limit": null, "data_limit": null, "data_fixed_limit": null},
+            "over_limit": false
+        }"#;
+
+        let report: FileReport = serde_json::from_str(json).unwrap();
+        assert_eq!(report.baseline_delta, None);
+    }
+}